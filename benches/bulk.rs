@@ -0,0 +1,62 @@
+// Compares `simd::decode_vu32_bulk` against one `vu32` call per value.
+//
+//     cargo bench --bench bulk --features simd
+
+use std::hint::black_box;
+use std::time::Instant;
+
+use varintrs::simd::decode_vu32_bulk;
+use varintrs::{Binary, ReadBinary, WriteBinary, MAX_VARINT_LEN32};
+
+fn scalar_decode(buf: &[u8], out: &mut [u32]) -> usize {
+    let mut pos = 0;
+    for o in out {
+        let (x, n) = Binary::vu32(&buf[pos..]);
+        assert!(n > 0);
+        *o = x;
+        pos += n as usize;
+    }
+    pos
+}
+
+fn encode(values: impl Iterator<Item = u32>) -> Vec<u8> {
+    let mut buf = Vec::new();
+    let mut tmp = [0u8; MAX_VARINT_LEN32];
+    for x in values {
+        let n = Binary::put_vu32(&mut tmp, x);
+        buf.extend_from_slice(&tmp[..n]);
+    }
+    buf
+}
+
+fn run(name: &str, buf: &[u8], out: &mut [u32], f: impl Fn(&[u8], &mut [u32]) -> usize) {
+    const ROUNDS: u32 = 50;
+    let start = Instant::now();
+    for _ in 0..ROUNDS {
+        black_box(f(black_box(buf), black_box(&mut *out)));
+    }
+    let elapsed = start.elapsed();
+    let mb = (buf.len() as f64 * ROUNDS as f64) / (1024.0 * 1024.0);
+    println!("{:<24} {:>10.1} MiB/s", name, mb / elapsed.as_secs_f64());
+}
+
+fn main() {
+    const COUNT: usize = 1_000_000;
+    let mut seed = 0x9e37_79b9_7f4a_7c15u64;
+    let mut next = move || {
+        seed ^= seed << 13;
+        seed ^= seed >> 7;
+        seed ^= seed << 17;
+        seed
+    };
+    let small = encode((0..COUNT).map(|_| (next() & 0x7f) as u32));
+    let mixed = encode((0..COUNT).map(|_| (next() as u32) >> (next() % 32)));
+
+    let mut out = vec![0u32; COUNT];
+    for (label, buf) in [("small", &small), ("mixed", &mixed)] {
+        run(&format!("{} scalar", label), buf, &mut out, scalar_decode);
+        run(&format!("{} bulk", label), buf, &mut out, |b, o| {
+            decode_vu32_bulk(b, o).unwrap()
+        });
+    }
+}
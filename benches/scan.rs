@@ -0,0 +1,71 @@
+// Compares `validate_vu64_buffer` against a plain byte-at-a-time scan.
+//
+//     cargo bench --bench scan --features fast-scan
+
+use std::hint::black_box;
+use std::time::Instant;
+
+use varintrs::{validate_vu64_buffer, Binary, WriteBytesVarExt, MAX_VARINT_LEN64};
+
+fn scalar_validate(buf: &[u8]) -> Option<usize> {
+    let mut count = 0;
+    let mut run = 0;
+    for &b in buf {
+        if run == MAX_VARINT_LEN64 - 1 {
+            if b > 1 {
+                return None;
+            }
+            count += 1;
+            run = 0;
+        } else if b < 0x80 {
+            count += 1;
+            run = 0;
+        } else {
+            run += 1;
+        }
+    }
+    if run != 0 {
+        return None;
+    }
+    Some(count)
+}
+
+fn encode(values: impl Iterator<Item = u64>) -> Vec<u8> {
+    let mut buf = Vec::new();
+    for x in values {
+        buf.write_vu64::<Binary>(x).unwrap();
+    }
+    buf
+}
+
+fn run(name: &str, buf: &[u8], f: impl Fn(&[u8]) -> usize) {
+    const ROUNDS: u32 = 50;
+    let start = Instant::now();
+    for _ in 0..ROUNDS {
+        black_box(f(black_box(buf)));
+    }
+    let elapsed = start.elapsed();
+    let mb = (buf.len() as f64 * ROUNDS as f64) / (1024.0 * 1024.0);
+    println!("{:<24} {:>10.1} MiB/s", name, mb / elapsed.as_secs_f64());
+}
+
+fn main() {
+    let mut seed = 0x9e37_79b9_7f4a_7c15u64;
+    let mut next = move || {
+        seed ^= seed << 13;
+        seed ^= seed >> 7;
+        seed ^= seed << 17;
+        seed
+    };
+    let small = encode((0..1_000_000).map(|_| next() & 0x7f));
+    let mixed = encode((0..1_000_000).map(|_| next() >> (next() % 64)));
+
+    for (label, buf) in [("small", &small), ("mixed", &mixed)] {
+        run(&format!("{} scalar", label), buf, |b| {
+            scalar_validate(b).unwrap()
+        });
+        run(&format!("{} validate", label), buf, |b| {
+            validate_vu64_buffer(b).unwrap()
+        });
+    }
+}
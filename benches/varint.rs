@@ -0,0 +1,145 @@
+// Encode and decode throughput for the varint and LEB128 codecs.
+//
+//     cargo bench --bench varint
+
+use std::fs::File;
+use std::hint::black_box;
+use std::io::{BufReader, Write};
+use std::time::Instant;
+
+use varintrs::{
+    append_vu64, vu64_fast, Binary, BufReadU8, ReadBinary, WriteBinary, MAX_VARINT_LEN64,
+};
+
+const COUNT: usize = 100_000;
+const ROUNDS: u32 = 20;
+
+// Runs `f` over the whole table `ROUNDS` times and reports per-value cost and
+// throughput over `bytes` encoded bytes per round.
+fn run(name: &str, bytes: usize, mut f: impl FnMut() -> usize) {
+    let start = Instant::now();
+    for _ in 0..ROUNDS {
+        black_box(f());
+    }
+    let secs = start.elapsed().as_secs_f64();
+    let ops = COUNT as f64 * ROUNDS as f64;
+    let mb = bytes as f64 * ROUNDS as f64 / (1024.0 * 1024.0);
+    println!(
+        "{:<28} {:>8.2} ns/op {:>10.1} MiB/s",
+        name,
+        secs * 1e9 / ops,
+        mb / secs
+    );
+}
+
+fn main() {
+    let mut seed = 0x9e37_79b9_7f4a_7c15u64;
+    let mut next = move || {
+        seed ^= seed << 13;
+        seed ^= seed >> 7;
+        seed ^= seed << 17;
+        seed
+    };
+    let small: Vec<u64> = (0..COUNT).map(|_| next() & 0x7f).collect();
+    let medium: Vec<u64> = (0..COUNT).map(|_| next() >> 36).collect();
+    let large: Vec<u64> = (0..COUNT).map(|_| next() | 1 << 63).collect();
+    // Nine in ten values fit in one or two bytes.
+    let short: Vec<u64> = (0..COUNT)
+        .map(|_| {
+            let x = next();
+            if x % 10 == 0 {
+                x >> 20
+            } else {
+                x & 0x3fff
+            }
+        })
+        .collect();
+
+    for (label, values) in [
+        ("small", &small),
+        ("short", &short),
+        ("medium", &medium),
+        ("10-byte", &large),
+    ] {
+        let mut encoded = Vec::new();
+        for &x in values {
+            append_vu64(&mut encoded, x);
+        }
+        let bytes = encoded.len();
+        let mut buf = [0u8; MAX_VARINT_LEN64];
+
+        run(&format!("{} put_vu64", label), bytes, || {
+            let mut n = 0;
+            for &x in values {
+                n += Binary::put_vu64(&mut buf, black_box(x));
+            }
+            n
+        });
+        run(&format!("{} put_leb128_u64", label), bytes, || {
+            let mut n = 0;
+            for &x in values {
+                n += Binary::put_leb128_u64(&mut buf, black_box(x));
+            }
+            n
+        });
+        run(&format!("{} vu64", label), bytes, || {
+            let mut pos = 0;
+            while pos < encoded.len() {
+                let (x, n) = Binary::vu64(&encoded[pos..]);
+                black_box(x);
+                pos += n as usize;
+            }
+            pos
+        });
+        run(&format!("{} vu64_fast", label), bytes, || {
+            let mut pos = 0;
+            while pos < encoded.len() {
+                let (x, n) = vu64_fast(&encoded[pos..]);
+                black_box(x);
+                pos += n as usize;
+            }
+            pos
+        });
+        run(&format!("{} read_leb128_u64", label), bytes, || {
+            let mut rdr = &encoded[..];
+            while !rdr.is_empty() {
+                black_box(Binary::read_leb128_u64(&mut rdr).unwrap());
+            }
+            encoded.len()
+        });
+        run(&format!("{} bulk encode", label), bytes, || {
+            let mut out = Vec::with_capacity(bytes);
+            for &x in values {
+                append_vu64(&mut out, x);
+            }
+            out.len()
+        });
+        run(&format!("{} bulk decode", label), bytes, || {
+            Binary::iter_vu64(&encoded).map(|r| r.unwrap()).sum::<u64>() as usize
+        });
+    }
+
+    // Decoding from a file through a BufReader: the blanket ReadU8 impl
+    // calls read_exact per byte, BufReadU8 reads the buffer directly.
+    let mut encoded = Vec::new();
+    for &x in &medium {
+        append_vu64(&mut encoded, x);
+    }
+    let path = std::env::temp_dir().join(format!("varintrs-bench-{}", std::process::id()));
+    File::create(&path).unwrap().write_all(&encoded).unwrap();
+    run("file BufReader read_vu64", encoded.len(), || {
+        let mut rdr = BufReader::new(File::open(&path).unwrap());
+        for _ in 0..COUNT {
+            black_box(Binary::read_vu64(&mut rdr));
+        }
+        encoded.len()
+    });
+    run("file BufReadU8 read_vu64", encoded.len(), || {
+        let mut rdr = BufReadU8::new(BufReader::new(File::open(&path).unwrap()));
+        for _ in 0..COUNT {
+            black_box(Binary::read_vu64(&mut rdr));
+        }
+        encoded.len()
+    });
+    std::fs::remove_file(&path).unwrap();
+}
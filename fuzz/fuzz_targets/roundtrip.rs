@@ -0,0 +1,12 @@
+// Decodes arbitrary bytes as canonical varints and checks that re-encoding
+// each accepted value reproduces its input bytes.
+//
+//     cargo +nightly fuzz run roundtrip
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    varintrs::fuzz_roundtrip(data);
+});
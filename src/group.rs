@@ -0,0 +1,110 @@
+//! Group varint encoding for `u32` values.
+//!
+//! Values are stored four at a time behind a control byte. Each 2-bit field
+//! of the control byte, starting from the least significant bits, holds the
+//! byte length minus one of the matching value, which follows in
+//! little-endian order. The last group is padded with zeros.
+
+use crate::VarintError;
+
+// Bytes needed to store `x`, from 1 to 4.
+#[inline]
+fn byte_len(x: u32) -> usize {
+    4 - (x | 1).leading_zeros() as usize / 8
+}
+
+/// Appends `values` to `out` in groups of four, padding the last group with
+/// zeros.
+pub fn encode_group_vu32(values: &[u32], out: &mut Vec<u8>) {
+    for group in values.chunks(4) {
+        let mut quad = [0u32; 4];
+        quad[..group.len()].copy_from_slice(group);
+
+        let control = out.len();
+        out.push(0);
+        let mut tag = 0u8;
+        for (i, &x) in quad.iter().enumerate() {
+            let n = byte_len(x);
+            tag |= ((n - 1) as u8) << (2 * i);
+            out.extend_from_slice(&x.to_le_bytes()[..n]);
+        }
+        out[control] = tag;
+    }
+}
+
+/// Decodes every group in `buf` and appends four values per group to `out`,
+/// including any zero padding written by [`encode_group_vu32`]; callers that
+/// know the original count should truncate to it. A group cut short is
+/// `UnexpectedEof`.
+pub fn decode_group_vu32(buf: &[u8], out: &mut Vec<u32>) -> Result<(), VarintError> {
+    let mut pos = 0;
+    while pos < buf.len() {
+        let tag = buf[pos];
+        pos += 1;
+        for i in 0..4 {
+            let n = ((tag >> (2 * i)) & 0b11) as usize + 1;
+            let bytes = buf.get(pos..pos + n).ok_or(VarintError::UnexpectedEof)?;
+            let mut le = [0u8; 4];
+            le[..n].copy_from_slice(bytes);
+            out.push(u32::from_le_bytes(le));
+            pos += n;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Binary, ReadBinary, WriteBinary, MAX_VARINT_LEN32};
+
+    const VALUES: [u32; 11] = [
+        0,
+        1,
+        255,
+        256,
+        65535,
+        65536,
+        16777215,
+        16777216,
+        u32::MAX,
+        300,
+        7,
+    ];
+
+    #[test]
+    fn test_group_vu32() {
+        let mut buf = Vec::new();
+        encode_group_vu32(&VALUES, &mut buf);
+        let mut out = Vec::new();
+        decode_group_vu32(&buf, &mut out).unwrap();
+        assert_eq!(out.len(), 12);
+        assert_eq!(out[11], 0);
+        out.truncate(VALUES.len());
+
+        let mut expected = Vec::new();
+        let mut scratch = [0u8; MAX_VARINT_LEN32];
+        for x in VALUES {
+            let n = Binary::put_vu32(&mut scratch, x);
+            expected.push(Binary::vu32(&scratch[..n]).0);
+        }
+        assert_eq!(out, expected);
+    }
+
+    #[test]
+    fn test_group_vu32_layout() {
+        let mut buf = Vec::new();
+        encode_group_vu32(&[1, 256, 65536, u32::MAX], &mut buf);
+        assert_eq!(buf[0], 0b11_10_01_00);
+        assert_eq!(buf.len(), 1 + 1 + 2 + 3 + 4);
+
+        let mut out = Vec::new();
+        assert!(matches!(
+            decode_group_vu32(&buf[..buf.len() - 1], &mut out),
+            Err(VarintError::UnexpectedEof)
+        ));
+        out.clear();
+        decode_group_vu32(&[], &mut out).unwrap();
+        assert!(out.is_empty());
+    }
+}
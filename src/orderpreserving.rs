@@ -0,0 +1,78 @@
+//! An order-preserving integer encoding for keys compared bytewise.
+//!
+//! A value is written as one byte holding the number of significant bytes,
+//! zero to eight, followed by those bytes big-endian. A longer encoding
+//! always holds a larger value, and equal lengths compare digit by digit, so
+//! `memcmp` of two encodings orders them like the numbers they hold.
+
+/// Maximum length of an order-preserving encoding.
+pub const MAX_ORDERED_LEN64: usize = 9;
+
+/// Number of bytes [`put_ordered_vu64`] writes for `x`.
+#[inline]
+pub const fn ordered_len(x: u64) -> usize {
+    1 + (64 - x.leading_zeros() as usize).div_ceil(8)
+}
+
+/// Encodes `x` into `buf` and returns the number of bytes written.
+/// If the buffer is too small, put_ordered_vu64 will panic.
+#[inline]
+pub fn put_ordered_vu64(buf: &mut [u8], x: u64) -> usize {
+    let n = ordered_len(x) - 1;
+    assert!(buf.len() > n, "buffer too small for varint");
+    buf[0] = n as u8;
+    buf[1..=n].copy_from_slice(&x.to_be_bytes()[8 - n..]);
+    n + 1
+}
+
+/// Decodes a value from the front of `buf` and returns it with the number of
+/// bytes read, or `(0, 0)` if `buf` is too short or the length byte is out of
+/// range.
+#[inline]
+pub fn ordered_vu64(buf: &[u8]) -> (u64, usize) {
+    let n = match buf.first() {
+        Some(&n) if (n as usize) < MAX_ORDERED_LEN64 && buf.len() > n as usize => n as usize,
+        _ => return (0, 0),
+    };
+    let mut bytes = [0u8; 8];
+    bytes[8 - n..].copy_from_slice(&buf[1..=n]);
+    (u64::from_be_bytes(bytes), n + 1)
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use crate::tests::uvar_test;
+
+    #[test]
+    fn test_ordered_vu64() {
+        let mut buf = [0u8; MAX_ORDERED_LEN64];
+        for x in uvar_test {
+            let n = put_ordered_vu64(&mut buf, x);
+            assert_eq!(n, ordered_len(x));
+            assert_eq!(ordered_vu64(&buf[..n]), (x, n));
+            assert_eq!(ordered_vu64(&buf[..n - 1]), (0, 0));
+        }
+        assert_eq!(put_ordered_vu64(&mut buf, 0), 1);
+        assert_eq!(buf[0], 0);
+        assert_eq!(ordered_vu64(&[9; 10]), (0, 0));
+    }
+
+    #[test]
+    fn test_ordered_vu64_sorts_bytewise() {
+        let mut encoded: Vec<Vec<u8>> = uvar_test
+            .iter()
+            .rev()
+            .map(|&x| {
+                let mut buf = [0u8; MAX_ORDERED_LEN64];
+                let n = put_ordered_vu64(&mut buf, x);
+                buf[..n].to_vec()
+            })
+            .collect();
+        encoded.sort();
+        let decoded: Vec<u64> = encoded.iter().map(|e| ordered_vu64(e).0).collect();
+        let mut expected = uvar_test.to_vec();
+        expected.sort();
+        assert_eq!(decoded, expected);
+    }
+}
@@ -0,0 +1,101 @@
+//! PrefixVarint: a little-endian varint whose length is fully described by
+//! the first byte.
+//!
+//! The number of trailing zero bits in the first byte is the number of bytes
+//! that follow it. An `n`-byte encoding (`n <= 8`) holds `7 * n` bits of the
+//! value above its `n` length bits; a first byte of zero is followed by the
+//! full eight-byte value, giving at most [`MAX_PREFIX_VARINT_LEN64`] bytes.
+
+/// Maximum length of a PrefixVarint-encoded 64-bit integer.
+pub const MAX_PREFIX_VARINT_LEN64: usize = 9;
+
+/// Number of bytes [`put_prefix_vu64`] writes for `x`.
+#[inline]
+pub const fn prefix_len(x: u64) -> usize {
+    let bits = 64 - (x | 1).leading_zeros() as usize;
+    if bits > 56 {
+        MAX_PREFIX_VARINT_LEN64
+    } else {
+        bits.div_ceil(7)
+    }
+}
+
+/// Encodes `x` into `buf` and returns the number of bytes written.
+/// If the buffer is too small, put_prefix_vu64 will panic.
+#[inline]
+pub fn put_prefix_vu64(buf: &mut [u8], x: u64) -> usize {
+    let n = prefix_len(x);
+    assert!(buf.len() >= n, "buffer too small for varint");
+    if n == MAX_PREFIX_VARINT_LEN64 {
+        buf[0] = 0;
+        buf[1..n].copy_from_slice(&x.to_le_bytes());
+    } else {
+        let v = (x << n) | (1 << (n - 1));
+        buf[..n].copy_from_slice(&v.to_le_bytes()[..n]);
+    }
+    n
+}
+
+/// Decodes a value from the front of `buf` and returns it with the number of
+/// bytes read. If `buf` is too short for the length announced by its first
+/// byte, the result is `(0, 0)`.
+#[inline]
+pub fn prefix_vu64(buf: &[u8]) -> (u64, usize) {
+    let first = match buf.first() {
+        Some(&b) => b,
+        None => return (0, 0),
+    };
+    if first == 0 {
+        return match buf.get(1..MAX_PREFIX_VARINT_LEN64) {
+            Some(bytes) => (u64::from_le_bytes(bytes.try_into().unwrap()), 9),
+            None => (0, 0),
+        };
+    }
+    let n = first.trailing_zeros() as usize + 1;
+    match buf.get(..n) {
+        Some(bytes) => {
+            let mut le = [0u8; 8];
+            le[..n].copy_from_slice(bytes);
+            (u64::from_le_bytes(le) >> n, n)
+        }
+        None => (0, 0),
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use crate::tests::uvar_test;
+
+    #[test]
+    fn test_prefix_vu64() {
+        let mut buf = [0u8; MAX_PREFIX_VARINT_LEN64];
+        for x in uvar_test {
+            let n = put_prefix_vu64(&mut buf, x);
+            assert_eq!(n, prefix_len(x));
+            assert_eq!(prefix_vu64(&buf[..n]), (x, n));
+            assert_eq!(prefix_vu64(&buf[..n - 1]), (0, 0));
+        }
+    }
+
+    #[test]
+    fn test_prefix_len_boundaries() {
+        let mut buf = [0u8; MAX_PREFIX_VARINT_LEN64];
+        for (x, len) in [
+            (0, 1),
+            (127, 1),
+            (128, 2),
+            (16383, 2),
+            (16384, 3),
+            ((1 << 56) - 1, 8),
+            (1 << 56, 9),
+            (u64::MAX, 9),
+        ] {
+            assert_eq!(put_prefix_vu64(&mut buf, x), len);
+        }
+        assert_eq!(put_prefix_vu64(&mut buf, 127), 1);
+        assert_eq!(buf[0], 0xff);
+        assert_eq!(put_prefix_vu64(&mut buf, 128), 2);
+        assert_eq!(&buf[..2], &[0x02, 0x02]);
+    }
+}
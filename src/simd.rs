@@ -0,0 +1,170 @@
+//! Bulk decoding of u32 varints, sixteen input bytes per step.
+//!
+//! On x86_64 the continuation bits of a 16-byte block are gathered with a
+//! single SSE2 `movemask`, and every value that ends inside the block is
+//! assembled from one 8-byte load without testing its bytes individually. Other targets
+//! use the scalar [`ReadBinary::vu32`] loop.
+
+use crate::{Binary, ReadBinary, VarintError, MAX_VARINT_LEN32};
+
+/// Decodes `out.len()` consecutive u32 varints from the front of `buf` and
+/// returns the number of bytes they occupied.
+///
+/// The result agrees with calling [`ReadBinary::vu32`] once per value: an
+/// encoding longer than five bytes, or a fifth byte carrying more than the
+/// top four bits, is [`VarintError::Overflow`], and running out of input is
+/// [`VarintError::UnexpectedEof`].
+pub fn decode_vu32_bulk(buf: &[u8], out: &mut [u32]) -> Result<usize, VarintError> {
+    let mut pos = 0;
+    let mut n = 0;
+    #[cfg(target_arch = "x86_64")]
+    // Eight bytes are loaded from the start of each value, so a block is
+    // only taken while that load stays inside `buf`.
+    while n < out.len() && pos + 24 <= buf.len() {
+        let block: &[u8; 16] = buf[pos..pos + 16].try_into().unwrap();
+        let more = continuation_mask(block);
+        if more == 0 {
+            let k = (out.len() - n).min(16);
+            for (o, &b) in out[n..n + k].iter_mut().zip(block) {
+                *o = b as u32;
+            }
+            n += k;
+            pos += k;
+            continue;
+        }
+        let mut ends = !more & 0xffff;
+        let mut start = 0;
+        while ends != 0 && n < out.len() {
+            let end = ends.trailing_zeros() as usize;
+            let len = end - start + 1;
+            if len > MAX_VARINT_LEN32 || (len == MAX_VARINT_LEN32 && block[end] > 0x0f) {
+                return Err(VarintError::Overflow);
+            }
+            let at = pos + start;
+            let w = u64::from_le_bytes(buf[at..at + 8].try_into().unwrap());
+            out[n] = pack_groups(w & ((1 << (8 * len)) - 1));
+            n += 1;
+            start = end + 1;
+            ends &= ends - 1;
+        }
+        if start == 0 {
+            // No value ends within the block, so the first one already has
+            // more than five bytes.
+            return Err(VarintError::Overflow);
+        }
+        pos += start;
+    }
+    for o in &mut out[n..] {
+        let (x, len) = Binary::vu32(&buf[pos..]);
+        if len == 0 {
+            return Err(VarintError::UnexpectedEof);
+        }
+        if len < 0 {
+            return Err(VarintError::Overflow);
+        }
+        *o = x;
+        pos += len as usize;
+    }
+    Ok(pos)
+}
+
+// Joins the 7-bit groups of an encoding of at most five bytes held in the
+// low bytes of `w`.
+#[cfg(target_arch = "x86_64")]
+#[inline]
+fn pack_groups(w: u64) -> u32 {
+    ((w & 0x7f)
+        | ((w >> 1) & (0x7f << 7))
+        | ((w >> 2) & (0x7f << 14))
+        | ((w >> 3) & (0x7f << 21))
+        | ((w >> 4) & (0x7f << 28))) as u32
+}
+
+// Bit i is set when byte i of the block has its continuation bit set.
+#[cfg(target_arch = "x86_64")]
+#[inline]
+fn continuation_mask(block: &[u8; 16]) -> u32 {
+    use core::arch::x86_64::{_mm_loadu_si128, _mm_movemask_epi8};
+    // SAFETY: the block is 16 readable bytes and the load is unaligned; SSE2
+    // is part of the x86_64 baseline.
+    unsafe { _mm_movemask_epi8(_mm_loadu_si128(block.as_ptr().cast())) as u32 }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use crate::WriteBinary;
+
+    fn encode(values: &[u32]) -> Vec<u8> {
+        let mut buf = Vec::new();
+        let mut tmp = [0u8; MAX_VARINT_LEN32];
+        for &x in values {
+            let n = Binary::put_vu32(&mut tmp, x);
+            buf.extend_from_slice(&tmp[..n]);
+        }
+        buf
+    }
+
+    fn decode_scalar(buf: &[u8], out: &mut [u32]) -> Result<usize, VarintError> {
+        let mut pos = 0;
+        for o in out {
+            let (x, n) = Binary::vu32(&buf[pos..]);
+            if n == 0 {
+                return Err(VarintError::UnexpectedEof);
+            }
+            if n < 0 {
+                return Err(VarintError::Overflow);
+            }
+            *o = x;
+            pos += n as usize;
+        }
+        Ok(pos)
+    }
+
+    #[test]
+    fn test_decode_vu32_bulk() {
+        let mut seed = 0x9e37_79b9_7f4a_7c15u64;
+        let values: Vec<u32> = (0..100_000)
+            .map(|_| {
+                seed ^= seed << 13;
+                seed ^= seed >> 7;
+                seed ^= seed << 17;
+                (seed as u32) >> (seed >> 59)
+            })
+            .collect();
+        let buf = encode(&values);
+        let mut bulk = vec![0u32; values.len()];
+        let mut scalar = vec![0u32; values.len()];
+        assert_eq!(decode_vu32_bulk(&buf, &mut bulk).unwrap(), buf.len());
+        assert_eq!(decode_scalar(&buf, &mut scalar).unwrap(), buf.len());
+        assert_eq!(bulk, scalar);
+        assert_eq!(bulk, values);
+    }
+
+    #[test]
+    fn test_decode_vu32_bulk_errors() {
+        let mut out = [0u32; 4];
+        let mut buf = vec![0u8; 40];
+        buf[1..6].copy_from_slice(&[0xff, 0xff, 0xff, 0xff, 0x10]);
+        assert!(matches!(
+            decode_vu32_bulk(&buf, &mut out),
+            Err(VarintError::Overflow)
+        ));
+        buf[1..7].copy_from_slice(&[0xff, 0xff, 0xff, 0xff, 0x80, 0x00]);
+        assert!(matches!(
+            decode_vu32_bulk(&buf, &mut out),
+            Err(VarintError::Overflow)
+        ));
+        assert!(matches!(
+            decode_vu32_bulk(&[0xff; 32], &mut out),
+            Err(VarintError::Overflow)
+        ));
+        assert!(matches!(
+            decode_vu32_bulk(&[0x01, 0x02, 0x80], &mut out),
+            Err(VarintError::UnexpectedEof)
+        ));
+        let buf = encode(&[u32::MAX; 4]);
+        assert_eq!(decode_vu32_bulk(&buf, &mut out).unwrap(), buf.len());
+        assert_eq!(out, [u32::MAX; 4]);
+    }
+}
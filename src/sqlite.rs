@@ -0,0 +1,102 @@
+//! The variable-length integer format used in SQLite database files.
+//!
+//! Values are stored big-endian in one to nine bytes. Each of the first eight
+//! bytes holds seven bits with the high bit set when more bytes follow; a
+//! ninth byte, used for values of 2^56 and above, contributes all eight of
+//! its bits.
+
+/// Maximum length of a SQLite varint.
+pub const MAX_SQLITE_VARINT_LEN: usize = 9;
+
+/// Number of bytes [`put_sqlite_varint`] writes for `x`.
+#[inline]
+pub const fn sqlite_varint_len(x: u64) -> usize {
+    let bits = 64 - (x | 1).leading_zeros() as usize;
+    if bits > 56 {
+        MAX_SQLITE_VARINT_LEN
+    } else {
+        bits.div_ceil(7)
+    }
+}
+
+/// Encodes `x` into `buf` and returns the number of bytes written.
+/// If the buffer is too small, put_sqlite_varint will panic.
+#[inline]
+pub fn put_sqlite_varint(buf: &mut [u8], mut x: u64) -> usize {
+    let n = sqlite_varint_len(x);
+    assert!(buf.len() >= n, "buffer too small for varint");
+    let mut groups = n;
+    if n == MAX_SQLITE_VARINT_LEN {
+        buf[8] = x as u8;
+        x >>= 8;
+        groups = 8;
+    }
+    for i in (0..groups).rev() {
+        buf[i] = (x & 0x7f) as u8 | 0x80;
+        x >>= 7;
+    }
+    if n < MAX_SQLITE_VARINT_LEN {
+        buf[n - 1] &= 0x7f;
+    }
+    n
+}
+
+/// Decodes a value from the front of `buf` and returns it with the number of
+/// bytes read, or `(0, 0)` if `buf` ends before the value does.
+#[inline]
+pub fn sqlite_varint(buf: &[u8]) -> (u64, usize) {
+    let mut x: u64 = 0;
+    for (i, &b) in buf.iter().enumerate() {
+        if i == MAX_SQLITE_VARINT_LEN - 1 {
+            return ((x << 8) | b as u64, MAX_SQLITE_VARINT_LEN);
+        }
+        x = (x << 7) | (b & 0x7f) as u64;
+        if b < 0x80 {
+            return (x, i + 1);
+        }
+    }
+    (0, 0)
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use crate::tests::uvar_test;
+
+    #[test]
+    fn test_sqlite_varint() {
+        let mut buf = [0u8; MAX_SQLITE_VARINT_LEN];
+        for x in uvar_test {
+            let n = put_sqlite_varint(&mut buf, x);
+            assert_eq!(n, sqlite_varint_len(x));
+            assert_eq!(sqlite_varint(&buf[..n]), (x, n));
+            assert_eq!(sqlite_varint(&buf[..n - 1]), (0, 0));
+        }
+    }
+
+    #[test]
+    fn test_sqlite_varint_known_values() {
+        let cases: [(u64, &[u8]); 8] = [
+            (0, &[0x00]),
+            (127, &[0x7f]),
+            (128, &[0x81, 0x00]),
+            (240, &[0x81, 0x70]),
+            (16383, &[0xff, 0x7f]),
+            (
+                (1 << 56) - 1,
+                &[0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x7f],
+            ),
+            (
+                1 << 56,
+                &[0x80, 0xc0, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x00],
+            ),
+            (u64::MAX, &[0xff; 9]),
+        ];
+        let mut buf = [0u8; MAX_SQLITE_VARINT_LEN];
+        for (x, bytes) in cases {
+            let n = put_sqlite_varint(&mut buf, x);
+            assert_eq!(&buf[..n], bytes);
+            assert_eq!(sqlite_varint(bytes), (x, bytes.len()));
+        }
+    }
+}
@@ -0,0 +1,73 @@
+// Prints the Go test vectors in src/lib.rs from Go's encoding/binary:
+//
+//	go run testdata/gen_vectors.go
+//
+// The output is the body of GO_UVARINT_VECTORS, GO_VARINT_VECTORS and
+// GO_UVARINT_ERRORS; run it through rustfmt after pasting.
+package main
+
+import (
+	"encoding/binary"
+	"fmt"
+	"math"
+	"strings"
+)
+
+func bytesLit(b []byte) string {
+	parts := make([]string, len(b))
+	for i, x := range b {
+		parts[i] = fmt.Sprintf("0x%02x", x)
+	}
+	return "&[" + strings.Join(parts, ", ") + "]"
+}
+
+func main() {
+	uvals := []uint64{
+		0, 1, 2, 127, 128, 255, 256, 300, 16383, 16384,
+		2097151, 2097152, 268435455, 268435456, 4294967295,
+		1 << 35, 1 << 42, 1 << 49, 1 << 56,
+		1<<63 - 1, 1 << 63, math.MaxUint64,
+	}
+	ivals := []int64{
+		0, 1, -1, 2, -2, 63, -64, 64, -65, 150, -150,
+		8191, -8192, 8192, math.MinInt32, math.MaxInt32,
+		-(1 << 62), math.MaxInt64, math.MinInt64,
+	}
+	buf := make([]byte, binary.MaxVarintLen64)
+
+	fmt.Println("// GO_UVARINT_VECTORS")
+	for _, x := range uvals {
+		n := binary.PutUvarint(buf, x)
+		fmt.Printf("(%d, %s),\n", x, bytesLit(buf[:n]))
+	}
+
+	fmt.Println("// GO_VARINT_VECTORS")
+	for _, x := range ivals {
+		n := binary.PutVarint(buf, x)
+		fmt.Printf("(%d, %s),\n", x, bytesLit(buf[:n]))
+	}
+
+	fmt.Println("// GO_UVARINT_ERRORS")
+	high := append(bytes(0xff, 9), 0x02)
+	inputs := [][]byte{
+		{},
+		{0x80},
+		bytes(0x80, 10),
+		bytes(0x80, 11),
+		bytes(0xff, 11),
+		append(bytes(0x80, 10), 0x00),
+		high,
+	}
+	for _, b := range inputs {
+		x, n := binary.Uvarint(b)
+		fmt.Printf("(%s, %d, %d),\n", bytesLit(b), x, n)
+	}
+}
+
+func bytes(b byte, n int) []byte {
+	out := make([]byte, n)
+	for i := range out {
+		out[i] = b
+	}
+	return out
+}